@@ -5,9 +5,16 @@
 // See the LICENSE file in the project root for license information.
 //
 
-use solana_client::client_error::ClientError;
+use base64::Engine;
+use solana_client::client_error::{ClientError, ClientErrorKind};
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcBlockConfig;
+use solana_commitment_config::CommitmentConfig;
+use solana_compute_budget_interface::ComputeBudgetInstruction;
 use solana_pubkey::Pubkey;
+use solana_transaction::versioned::VersionedTransaction;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionDetails, UiTransactionEncoding};
 use std::collections::HashMap;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -21,8 +28,23 @@ pub enum PriorityFeeLevel {
     Custom(u64),
 }
 
+// The previous hardcoded chunking (`chunks(150).take(3)`) kept roughly the last 450 samples; this
+// is the default lookback so behavior is unchanged when callers don't opt into a custom window.
+const DEFAULT_LOOKBACK_SLOTS: usize = 450;
+
 #[allow(clippy::result_large_err)]
 pub async fn get_priority_fee_estimate(client: &RpcClient, addresses: Vec<Pubkey>, level: PriorityFeeLevel) -> Result<u64, ClientError> {
+    get_priority_fee_estimate_with_options(client, addresses, level, None, false).await
+}
+
+#[allow(clippy::result_large_err)]
+pub async fn get_priority_fee_estimate_with_options(
+    client: &RpcClient,
+    addresses: Vec<Pubkey>,
+    level: PriorityFeeLevel,
+    lookback_slots: Option<usize>,
+    include_vote: bool,
+) -> Result<u64, ClientError> {
     if level == PriorityFeeLevel::None {
         return Ok(0);
     }
@@ -31,14 +53,23 @@ pub async fn get_priority_fee_estimate(client: &RpcClient, addresses: Vec<Pubkey
         return Ok(fee);
     }
 
-    let recent_prioritization_fees = get_priority_fee_levels_estimate(client, addresses).await?;
+    let recent_prioritization_fees = get_priority_fee_levels_estimate(client, addresses, lookback_slots, include_vote).await?;
     let fee = recent_prioritization_fees.get(&level).copied().unwrap_or(0);
 
     Ok(fee)
 }
 
+/// `lookback_slots` bounds how many of the most recent slots' samples are used (None keeps the
+/// historical default of ~450). `include_vote` controls whether the RPC is queried with the given
+/// `addresses` (false, the default — restricts aggregates to the caller's writable accounts/programs)
+/// or with no address filter (true — pulls the broader, vote-inflated global fee market).
 #[allow(clippy::result_large_err)]
-pub async fn get_priority_fee_levels_estimate(client: &RpcClient, addresses: Vec<Pubkey>) -> Result<HashMap<PriorityFeeLevel, u64>, ClientError> {
+pub async fn get_priority_fee_levels_estimate(
+    client: &RpcClient,
+    addresses: Vec<Pubkey>,
+    lookback_slots: Option<usize>,
+    include_vote: bool,
+) -> Result<HashMap<PriorityFeeLevel, u64>, ClientError> {
     let mut priority_fees: HashMap<PriorityFeeLevel, u64> = [
         (PriorityFeeLevel::None, 0),
         (PriorityFeeLevel::Low, 0),
@@ -50,20 +81,19 @@ pub async fn get_priority_fee_levels_estimate(client: &RpcClient, addresses: Vec
     .into_iter()
     .collect();
 
-    let recent_prioritization_fees = client.get_recent_prioritization_fees(&addresses).await?;
+    let queried_addresses = if include_vote { &[] } else { addresses.as_slice() };
+    let recent_prioritization_fees = client.get_recent_prioritization_fees(queried_addresses).await?;
     if recent_prioritization_fees.is_empty() {
         return Ok(priority_fees);
     }
 
+    let lookback_slots = lookback_slots.unwrap_or(DEFAULT_LOOKBACK_SLOTS);
     let mut sorted_fees: Vec<_> = recent_prioritization_fees.into_iter().collect();
     sorted_fees.sort_by(|a, b| b.slot.cmp(&a.slot));
-    let chunk_size = 150;
-    let chunks: Vec<_> = sorted_fees.chunks(chunk_size).take(3).collect();
-    let mut percentiles: HashMap<u8, u64> = HashMap::new();
-    for chunk in chunks.iter() {
-        let fees: Vec<u64> = chunk.iter().map(|fee| fee.prioritization_fee).collect();
-        percentiles = calculate_percentiles(&fees);
-    }
+    sorted_fees.truncate(lookback_slots);
+
+    let fees: Vec<u64> = sorted_fees.iter().map(|fee| fee.prioritization_fee).collect();
+    let percentiles = calculate_percentiles(&fees);
 
     priority_fees.insert(PriorityFeeLevel::Low, *percentiles.get(&70).unwrap_or(&0));
     priority_fees.insert(PriorityFeeLevel::Medium, *percentiles.get(&75).unwrap_or(&0));
@@ -87,3 +117,198 @@ fn calculate_percentiles(fees: &[u64]) -> HashMap<u8, u64> {
         })
         .collect()
 }
+
+/// Selects the algorithm used to derive percentile priority fees.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PriorityFeeEstimateMode {
+    /// Samples `get_recent_prioritization_fees` account-level aggregates. This is the existing
+    /// behavior: fast, but treats every transaction's fee equally and cannot exclude votes.
+    AccountSampling,
+    /// Pulls the last `lookback_blocks` full blocks and weights the fee distribution by each
+    /// non-vote transaction's consumed compute units, so fees better reflect what actually
+    /// competes for block space.
+    CuWeightedBlocks { lookback_blocks: usize },
+}
+
+impl Default for PriorityFeeEstimateMode {
+    fn default() -> Self {
+        PriorityFeeEstimateMode::AccountSampling
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub async fn get_priority_fee_levels_estimate_with_mode(
+    client: &RpcClient,
+    addresses: Vec<Pubkey>,
+    mode: PriorityFeeEstimateMode,
+) -> Result<HashMap<PriorityFeeLevel, u64>, ClientError> {
+    match mode {
+        PriorityFeeEstimateMode::AccountSampling => get_priority_fee_levels_estimate(client, addresses, None, false).await,
+        PriorityFeeEstimateMode::CuWeightedBlocks { lookback_blocks } => cu_weighted_percentiles_from_blocks(client, lookback_blocks).await,
+    }
+}
+
+/// Same as [`get_priority_fee_estimate_with_options`], but dispatches through
+/// [`PriorityFeeEstimateMode`] instead of always using account sampling.
+#[allow(clippy::result_large_err)]
+pub async fn get_priority_fee_estimate_with_mode(
+    client: &RpcClient,
+    addresses: Vec<Pubkey>,
+    level: PriorityFeeLevel,
+    mode: PriorityFeeEstimateMode,
+) -> Result<u64, ClientError> {
+    if level == PriorityFeeLevel::None {
+        return Ok(0);
+    }
+
+    if let PriorityFeeLevel::Custom(fee) = level {
+        return Ok(fee);
+    }
+
+    let recent_prioritization_fees = get_priority_fee_levels_estimate_with_mode(client, addresses, mode).await?;
+    let fee = recent_prioritization_fees.get(&level).copied().unwrap_or(0);
+
+    Ok(fee)
+}
+
+fn empty_priority_fees() -> HashMap<PriorityFeeLevel, u64> {
+    [
+        (PriorityFeeLevel::None, 0),
+        (PriorityFeeLevel::Low, 0),
+        (PriorityFeeLevel::Medium, 0),
+        (PriorityFeeLevel::High, 0),
+        (PriorityFeeLevel::VeryHigh, 0),
+        (PriorityFeeLevel::Ultimate, 0),
+    ]
+    .into_iter()
+    .collect()
+}
+
+// How many slots we're willing to walk back per requested block, to bound the work when blocks
+// are missing (skipped slots) without risking an unbounded scan down to slot 0.
+const MAX_SLOTS_SCANNED_PER_BLOCK: usize = 3;
+// If `getBlock` fails this many times in a row, the cluster likely has block history disabled
+// (or is rate-limiting us) rather than just skipping the odd slot, so give up and surface an error.
+const MAX_CONSECUTIVE_BLOCK_ERRORS: usize = 20;
+
+/// Builds the fee distribution weighted by consumed compute units over the last `lookback_blocks`
+/// full blocks, excluding vote transactions, and reads off each level's percentile by walking the
+/// (fee, cu)-pairs ascending by fee until the requested share of total CU is reached.
+async fn cu_weighted_percentiles_from_blocks(client: &RpcClient, lookback_blocks: usize) -> Result<HashMap<PriorityFeeLevel, u64>, ClientError> {
+    let mut priority_fees = empty_priority_fees();
+
+    let block_config = RpcBlockConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        transaction_details: Some(TransactionDetails::Full),
+        rewards: Some(false),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let mut weighted_fees: Vec<(u64, u64)> = Vec::new();
+    let mut total_cu: u128 = 0;
+    let mut blocks_seen = 0;
+    let mut consecutive_errors = 0;
+    let mut slot = client.get_slot().await?;
+
+    let max_slots_scanned = lookback_blocks.saturating_mul(MAX_SLOTS_SCANNED_PER_BLOCK).max(1);
+    let mut slots_scanned = 0;
+
+    while blocks_seen < lookback_blocks && slot > 0 && slots_scanned < max_slots_scanned {
+        slots_scanned += 1;
+
+        match client.get_block_with_config(slot, block_config).await {
+            Ok(block) => {
+                consecutive_errors = 0;
+                blocks_seen += 1;
+
+                for tx in block.transactions.unwrap_or_default() {
+                    if let Some((fee, cu_consumed)) = extract_non_vote_fee_and_cu(&tx) {
+                        total_cu += cu_consumed as u128;
+                        weighted_fees.push((fee, cu_consumed));
+                    }
+                }
+            }
+            Err(_) => {
+                consecutive_errors += 1;
+                if consecutive_errors >= MAX_CONSECUTIVE_BLOCK_ERRORS {
+                    return Err(ClientError {
+                        request: None,
+                        kind: ClientErrorKind::Custom(format!(
+                            "giving up on CU-weighted priority fee estimate after {consecutive_errors} consecutive getBlock failures \
+                             (the RPC endpoint may have block history disabled)"
+                        )),
+                    });
+                }
+            }
+        }
+
+        slot = slot.saturating_sub(1);
+    }
+
+    if weighted_fees.is_empty() {
+        return Ok(priority_fees);
+    }
+
+    weighted_fees.sort_unstable_by_key(|(fee, _)| *fee);
+
+    for (level, percentile) in [
+        (PriorityFeeLevel::Low, 70u8),
+        (PriorityFeeLevel::Medium, 75),
+        (PriorityFeeLevel::High, 80),
+        (PriorityFeeLevel::VeryHigh, 85),
+        (PriorityFeeLevel::Ultimate, 95),
+    ] {
+        let target_cu = (percentile as f64 / 100.0 * total_cu as f64) as u128;
+        let mut cumulative_cu: u128 = 0;
+        let mut fee_at_percentile = weighted_fees.last().map(|(fee, _)| *fee).unwrap_or(0);
+        for (fee, cu_consumed) in &weighted_fees {
+            cumulative_cu += *cu_consumed as u128;
+            if cumulative_cu >= target_cu {
+                fee_at_percentile = *fee;
+                break;
+            }
+        }
+
+        priority_fees.insert(level, fee_at_percentile);
+    }
+
+    Ok(priority_fees)
+}
+
+/// Decodes a block transaction, drops it if it is vote-only, and returns its declared
+/// `SetComputeUnitPrice` fee (0 if absent) together with its actual consumed compute units.
+fn extract_non_vote_fee_and_cu(tx: &EncodedTransactionWithStatusMeta) -> Option<(u64, u64)> {
+    let cu_consumed = match tx.meta.as_ref()?.compute_units_consumed {
+        OptionSerializer::Some(cu_consumed) => cu_consumed,
+        _ => return None,
+    };
+
+    let EncodedTransaction::Binary(data, UiTransactionEncoding::Base64) = &tx.transaction else {
+        return None;
+    };
+    let raw_transaction = base64::engine::general_purpose::STANDARD.decode(data).ok()?;
+    let versioned_tx: VersionedTransaction = bincode::deserialize(&raw_transaction).ok()?;
+
+    let account_keys = versioned_tx.message.static_account_keys();
+    let instructions = versioned_tx.message.instructions();
+
+    let is_vote_only = !instructions.is_empty()
+        && instructions
+            .iter()
+            .all(|ix| account_keys.get(ix.program_id_index as usize) == Some(&solana_sdk_ids::vote::id()));
+    if is_vote_only {
+        return None;
+    }
+
+    let fee = instructions
+        .iter()
+        .filter(|ix| account_keys.get(ix.program_id_index as usize) == Some(&solana_sdk_ids::compute_budget::id()))
+        .find_map(|ix| match bincode::deserialize(&ix.data) {
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => Some(price),
+            _ => None,
+        })
+        .unwrap_or(0);
+
+    Some((fee, cu_consumed))
+}
@@ -0,0 +1,177 @@
+//
+// Copyright (c) Cryptic Dot
+//
+// Licensed under FusionAMM SDK Source-Available License v1.0
+// See the LICENSE file in the project root for license information.
+//
+
+use crate::priority_fee::PriorityFeeLevel;
+use futures::{SinkExt, StreamExt};
+use log::warn;
+use serde::Deserialize;
+use solana_clock::Slot;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+const DEFAULT_WINDOW_SLOTS: usize = 150;
+const RECONNECT_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Per-block prioritization fee percentiles, as published by a `blockPrioritizationFeesSubscribe` feed.
+#[derive(Clone, Debug, Default)]
+pub struct BlockPrioFees {
+    pub p70: u64,
+    pub p75: u64,
+    pub p80: u64,
+    pub p85: u64,
+    pub p95: u64,
+}
+
+impl BlockPrioFees {
+    fn level(&self, level: PriorityFeeLevel) -> u64 {
+        match level {
+            PriorityFeeLevel::None => 0,
+            PriorityFeeLevel::Low => self.p70,
+            PriorityFeeLevel::Medium => self.p75,
+            PriorityFeeLevel::High => self.p80,
+            PriorityFeeLevel::VeryHigh => self.p85,
+            PriorityFeeLevel::Ultimate => self.p95,
+            PriorityFeeLevel::Custom(fee) => fee,
+        }
+    }
+}
+
+/// A source of priority fee percentile estimates, as an alternative to issuing a fresh
+/// `get_recent_prioritization_fees` RPC call on every send.
+#[async_trait::async_trait]
+pub trait PriorityFeeSource: Send + Sync {
+    /// Returns the current percentile estimate for `level`, or None if no data has arrived yet.
+    async fn estimate(&self, level: PriorityFeeLevel) -> Option<u64>;
+}
+
+/// Subscribes to a lite-rpc-style `blockPrioritizationFeesSubscribe` WebSocket feed and keeps a
+/// rolling window of the most recent blocks' per-level fee percentiles, evicting older slots.
+pub struct WsPriorityFeeSource {
+    window: Arc<RwLock<BTreeMap<Slot, BlockPrioFees>>>,
+    _subscription: JoinHandle<()>,
+}
+
+#[derive(Deserialize)]
+struct BlockPrioritizationFeesNotification {
+    params: BlockPrioritizationFeesParams,
+}
+
+#[derive(Deserialize)]
+struct BlockPrioritizationFeesParams {
+    result: BlockPrioritizationFeesResult,
+}
+
+#[derive(Deserialize)]
+struct BlockPrioritizationFeesResult {
+    slot: Slot,
+    #[serde(rename = "prioritizationFeePercentiles")]
+    percentiles: PercentilesPayload,
+}
+
+#[derive(Deserialize)]
+struct PercentilesPayload {
+    p70: u64,
+    p75: u64,
+    p80: u64,
+    p85: u64,
+    p95: u64,
+}
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+impl WsPriorityFeeSource {
+    /// Connects to `ws_url` and starts the background subscription task. Pass `window_size` to
+    /// override how many recent slots are retained; defaults to 150.
+    ///
+    /// If the socket closes or errors after the initial connection, the task reconnects and
+    /// resubscribes with an exponential backoff rather than leaving `estimate()` serving
+    /// increasingly stale fees forever.
+    pub async fn connect(ws_url: &str, window_size: Option<usize>) -> Result<Self, tokio_tungstenite::tungstenite::Error> {
+        let window_size = window_size.unwrap_or(DEFAULT_WINDOW_SLOTS);
+        let mut ws_stream = subscribe(ws_url).await?;
+
+        let window = Arc::new(RwLock::new(BTreeMap::new()));
+        let window_for_task = window.clone();
+        let ws_url = ws_url.to_string();
+        let subscription = tokio::spawn(async move {
+            let mut backoff = RECONNECT_MIN_BACKOFF;
+            loop {
+                while let Some(message) = ws_stream.next().await {
+                    let Ok(Message::Text(text)) = message else { continue };
+                    let Ok(notification) = serde_json::from_str::<BlockPrioritizationFeesNotification>(&text) else {
+                        continue;
+                    };
+
+                    let result = notification.params.result;
+                    let fees = BlockPrioFees {
+                        p70: result.percentiles.p70,
+                        p75: result.percentiles.p75,
+                        p80: result.percentiles.p80,
+                        p85: result.percentiles.p85,
+                        p95: result.percentiles.p95,
+                    };
+
+                    let mut window = window_for_task.write().await;
+                    window.insert(result.slot, fees);
+                    while window.len() > window_size {
+                        if let Some(&oldest) = window.keys().next() {
+                            window.remove(&oldest);
+                        }
+                    }
+                    drop(window);
+
+                    backoff = RECONNECT_MIN_BACKOFF;
+                }
+
+                warn!(target: "log", "Priority fee WebSocket feed closed, reconnecting in {:?}", backoff);
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+
+                match subscribe(&ws_url).await {
+                    Ok(stream) => ws_stream = stream,
+                    Err(e) => warn!(target: "log", "Failed to reconnect priority fee WebSocket feed: {}", e),
+                }
+            }
+        });
+
+        Ok(Self {
+            window,
+            _subscription: subscription,
+        })
+    }
+}
+
+/// Opens the WebSocket connection and sends the `blockPrioritizationFeesSubscribe` request.
+async fn subscribe(ws_url: &str) -> Result<WsStream, tokio_tungstenite::tungstenite::Error> {
+    let (mut ws_stream, _) = connect_async(ws_url).await?;
+
+    let subscribe_request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "blockPrioritizationFeesSubscribe",
+        "params": [],
+    });
+    ws_stream.send(Message::Text(subscribe_request.to_string())).await?;
+
+    Ok(ws_stream)
+}
+
+#[async_trait::async_trait]
+impl PriorityFeeSource for WsPriorityFeeSource {
+    async fn estimate(&self, level: PriorityFeeLevel) -> Option<u64> {
+        let window = self.window.read().await;
+        window.values().next_back().map(|fees| fees.level(level))
+    }
+}
@@ -6,7 +6,9 @@
 //
 
 use crate::jito::{get_jito_api_url_by_region, poll_jito_bundle_statuses, send_jito_bundle, JITO_TIP_ACCOUNTS, MIN_JITO_TIP_LAMPORTS};
-use crate::priority_fee::get_priority_fee_estimate;
+use crate::priority_fee::{get_priority_fee_estimate_with_mode, get_priority_fee_estimate_with_options, PriorityFeeEstimateMode};
+use crate::priority_fee_stream::PriorityFeeSource;
+use crate::tpu_sender::{send_transaction_via_tpu, SmartTxTpuConfig};
 use crate::PriorityFeeLevel;
 use log::warn;
 use rand::Rng;
@@ -36,6 +38,7 @@ use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+const BASE_FEE_LAMPORTS_PER_SIGNATURE: u64 = 5000;
 const DEFAULT_TRANSACTION_TIMEOUT_SECONDS: u64 = 60;
 const DEFAULT_POLLING_INTERVAL_SECONDS: u64 = 2;
 const DEFAULT_COMPUTE_UNIT_MARGIN_MULTIPLIER: f64 = 1.15;
@@ -46,6 +49,8 @@ pub struct SmartTxConfig {
     pub priority_fee: Option<SmartTxPriorityFeeConfig>,
     /// Jito options. Set to None if jito is not used.
     pub jito: Option<SmartTxJitoConfig>,
+    /// Direct TPU/QUIC submission options. Set to None to send via RPC `send_transaction_with_config` instead.
+    pub tpu: Option<SmartTxTpuConfig>,
     /// This value is only used if estimation fails.
     pub default_compute_unit_limit: u32,
     /// Multiplier for CU estimation during simulation.
@@ -64,6 +69,14 @@ pub struct SmartTxConfig {
     pub transaction_timeout: Option<Duration>,
     /// The blockhash to use for the transaction. If set to None, the recent one will be fetched.
     pub blockhash: Option<Hash>,
+    /// If set, the signed transaction is re-submitted on this interval while waiting for
+    /// confirmation, stopping once it confirms, the timeout elapses, or the blockhash expires.
+    /// Blockhash expiry is only detected when `blockhash` is None and the blockhash is fetched here.
+    /// Only applies to the direct-RPC send path. Disabled (None) by default.
+    pub rebroadcast_interval: Option<Duration>,
+    /// Hard ceiling on the transaction's projected total cost (base fee + priority fee + Jito
+    /// tip). If the projected cost exceeds this, the transaction is not sent. Disabled (None) by default.
+    pub max_total_fee_lamports: Option<u64>,
 }
 
 impl Default for SmartTxConfig {
@@ -71,6 +84,7 @@ impl Default for SmartTxConfig {
         Self {
             priority_fee: None,
             jito: None,
+            tpu: None,
             default_compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
             compute_unit_margin_multiplier: DEFAULT_COMPUTE_UNIT_MARGIN_MULTIPLIER,
             disable_simulation: false,
@@ -80,6 +94,8 @@ impl Default for SmartTxConfig {
             polling_interval: None,
             transaction_timeout: None,
             blockhash: None,
+            rebroadcast_interval: None,
+            max_total_fee_lamports: None,
         }
     }
 }
@@ -89,6 +105,19 @@ pub struct SmartTxPriorityFeeConfig {
     pub fee_level: PriorityFeeLevel,
     pub fee_min: Option<u64>,
     pub fee_max: Option<u64>,
+    /// When set, the estimate is read from this continuously-updated source instead of issuing a
+    /// fresh `get_recent_prioritization_fees` RPC call on every send.
+    pub source: Option<Arc<dyn PriorityFeeSource>>,
+    /// How many of the most recent slots' samples to use for the estimate. None keeps the
+    /// historical default (~450 samples).
+    pub lookback_slots: Option<usize>,
+    /// Query the RPC without an address filter, which includes the broader, vote-inflated global
+    /// fee market instead of just the caller's writable program/account set. Defaults to false.
+    pub include_vote: bool,
+    /// Algorithm used to derive the estimate. Defaults to `AccountSampling`, which honors
+    /// `lookback_slots`/`include_vote` above. `CuWeightedBlocks` ignores both in favor of its own
+    /// `lookback_blocks`.
+    pub estimate_mode: PriorityFeeEstimateMode,
 }
 
 #[derive(Clone)]
@@ -128,6 +157,10 @@ pub struct SmartTxResult {
     pub priority_fee: u64,
     /// Jito bundle id if the transaction has been sent via Jito.
     pub jito_bundle_id: Option<String>,
+    /// Number of times the transaction was rebroadcast while waiting for confirmation.
+    pub rebroadcast_count: u32,
+    /// Projected total cost (base fee + priority fee + Jito tip), in lamports.
+    pub total_fee_lamports: u64,
     /// Various elapsed times for statistical purposes.
     pub elapsed_time: SmartTxElapsedTime,
 }
@@ -146,6 +179,10 @@ pub enum SmartTransactionError {
     RpcClientError(#[from] ClientError),
     #[error("JitoClientError: {0}")]
     JitoClientError(String),
+    #[error("the blockhash used for this transaction expired before it could be confirmed")]
+    BlockhashExpired,
+    #[error("projected fee of {projected} lamports exceeds the configured budget of {budget} lamports")]
+    FeeBudgetExceeded { projected: u64, budget: u64 },
 }
 
 pub async fn send_smart_transaction(
@@ -170,14 +207,27 @@ pub async fn send_smart_transaction(
     if let Some(fee_config) = tx_config.priority_fee {
         // Priority fee is not required for jito bundles.
         if tx_config.jito.is_none() && fee_config.fee_level != PriorityFeeLevel::None {
+            let streamed_estimate = match &fee_config.source {
+                Some(source) => source.estimate(fee_config.fee_level).await,
+                None => None,
+            };
+
             priority_fee = if let PriorityFeeLevel::Custom(fee) = fee_config.fee_level {
                 fee
+            } else if let Some(estimate) = streamed_estimate {
+                estimate
             } else {
                 let mut accounts: Vec<Pubkey> = instructions.iter().flat_map(|ix| ix.accounts.iter()).map(|a| a.pubkey).collect();
                 let programs: Vec<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
                 accounts.extend(programs);
 
-                get_priority_fee_estimate(client, accounts, fee_config.fee_level).await?
+                match fee_config.estimate_mode {
+                    PriorityFeeEstimateMode::AccountSampling => {
+                        get_priority_fee_estimate_with_options(client, accounts, fee_config.fee_level, fee_config.lookback_slots, fee_config.include_vote)
+                            .await?
+                    }
+                    mode => get_priority_fee_estimate_with_mode(client, accounts, fee_config.fee_level, mode).await?,
+                }
             };
 
             if let Some(fee_min) = fee_config.fee_min {
@@ -198,12 +248,14 @@ pub async fn send_smart_transaction(
     all_instructions.extend(instructions);
 
     // Add a tip instruction to the end of the instructions list if jito tips are provided.
+    let mut jito_tip_lamports = 0;
     if let Some(jito_config) = tx_config.jito.clone() {
         let rnd = rand::rng().random_range(0..JITO_TIP_ACCOUNTS.len());
         let tip_amount = jito_config.tips.max(MIN_JITO_TIP_LAMPORTS);
         let random_tip_account = Pubkey::from_str(JITO_TIP_ACCOUNTS[rnd]).unwrap();
         let tip_instruction = transfer(payer, &random_tip_account, tip_amount);
         all_instructions.push(tip_instruction);
+        jito_tip_lamports = tip_amount;
     }
 
     let signers_copy: Vec<Keypair> = signers.iter().map(|keypair| keypair.insecure_clone()).collect();
@@ -271,13 +323,32 @@ pub async fn send_smart_transaction(
     //
     // Recreate the transaction with the updated CU limit.
     //
-    let latest_blockhash = if let Some(blockhash) = tx_config.blockhash {
-        blockhash
+    let (latest_blockhash, last_valid_block_height) = if let Some(blockhash) = tx_config.blockhash {
+        (blockhash, None)
     } else {
-        client.get_latest_blockhash().await?
+        let (blockhash, last_valid_block_height) = client.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed()).await?;
+        (blockhash, Some(last_valid_block_height))
     };
 
     let versioned_message = VersionedMessage::V0(v0::Message::try_compile(payer, &all_instructions, &lookup_tables, latest_blockhash)?);
+
+    // Base fee (5000 lamports per required signature) + the prioritization fee derived from the
+    // compute-unit price times the CU limit + any Jito tip. The signature count is read off the
+    // compiled message header rather than `signers.len()`, since that's what the cluster actually
+    // charges for.
+    let base_fee_lamports = BASE_FEE_LAMPORTS_PER_SIGNATURE * versioned_message.header().num_required_signatures as u64;
+    let priority_fee_lamports = (priority_fee as u128 * cu_limit as u128).div_ceil(1_000_000) as u64;
+    let projected_total_fee_lamports = base_fee_lamports + priority_fee_lamports + jito_tip_lamports;
+
+    if let Some(max_total_fee_lamports) = tx_config.max_total_fee_lamports {
+        if projected_total_fee_lamports > max_total_fee_lamports {
+            return Err(SmartTransactionError::FeeBudgetExceeded {
+                projected: projected_total_fee_lamports,
+                budget: max_total_fee_lamports,
+            });
+        }
+    }
+
     let transaction = VersionedTransaction::try_new(versioned_message, &signers_copy)?;
 
     elapsed_time.prepare_and_simulate = start.elapsed();
@@ -315,6 +386,8 @@ pub async fn send_smart_transaction(
                 signature: Some(signature),
                 priority_fee,
                 jito_bundle_id: Some(jito_bundle_id),
+                rebroadcast_count: 0,
+                total_fee_lamports: projected_total_fee_lamports,
                 elapsed_time,
             })
         } else {
@@ -322,9 +395,42 @@ pub async fn send_smart_transaction(
                 signature: None,
                 priority_fee,
                 jito_bundle_id: Some(jito_bundle_id),
+                rebroadcast_count: 0,
+                total_fee_lamports: projected_total_fee_lamports,
                 elapsed_time,
             })
         }
+    } else if let Some(tpu_config) = tx_config.tpu {
+        let signature = *transaction.signatures.first().ok_or(SmartTransactionError::SigningError(SignerError::KeypairPubkeyMismatch))?;
+        let wire_transaction = bincode::serialize(&transaction).expect("Failed to serialize transaction");
+
+        let current_slot = client.get_slot().await?;
+
+        send_transaction_via_tpu(
+            &tpu_config.leader_cache,
+            current_slot,
+            tpu_config.leader_fanout,
+            tpu_config.identity.as_deref(),
+            &wire_transaction,
+        )
+        .await?;
+
+        elapsed_time.send = start.elapsed();
+
+        // Wait for the confirmation.
+        if tx_config.wait_for_confirmation {
+            poll_transaction_confirmation(client, signature, polling_interval, transaction_timeout).await?;
+            elapsed_time.confirm = start.elapsed();
+        }
+
+        Ok(SmartTxResult {
+            signature: Some(signature),
+            priority_fee,
+            jito_bundle_id: None,
+            rebroadcast_count: 0,
+            total_fee_lamports: projected_total_fee_lamports,
+            elapsed_time,
+        })
     } else {
         let send_config = RpcSendTransactionConfig {
             skip_preflight: true,
@@ -338,9 +444,20 @@ pub async fn send_smart_transaction(
 
         elapsed_time.send = start.elapsed();
 
-        // Wait for the confirmation.
+        // Wait for the confirmation, rebroadcasting on an interval until it lands, the timeout
+        // elapses, or the blockhash used to build the transaction expires.
+        let mut rebroadcast_count = 0;
         if tx_config.wait_for_confirmation {
-            poll_transaction_confirmation(client, signature, polling_interval, transaction_timeout).await?;
+            rebroadcast_count = confirm_with_rebroadcast(
+                client,
+                &transaction,
+                signature,
+                polling_interval,
+                transaction_timeout,
+                tx_config.rebroadcast_interval,
+                last_valid_block_height,
+            )
+            .await?;
             elapsed_time.confirm = start.elapsed();
         }
 
@@ -348,11 +465,69 @@ pub async fn send_smart_transaction(
             signature: Some(signature),
             priority_fee,
             jito_bundle_id: None,
+            rebroadcast_count,
+            total_fee_lamports: projected_total_fee_lamports,
             elapsed_time,
         })
     }
 }
 
+/// Polls for transaction confirmation, re-submitting the exact same signed transaction on
+/// `rebroadcast_interval` in the meantime. The loop stops as soon as the transaction confirms,
+/// `timeout` elapses, or (when `last_valid_block_height` is known) the blockhash expires.
+#[allow(clippy::too_many_arguments)]
+async fn confirm_with_rebroadcast(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    tx_sig: Signature,
+    interval: Duration,
+    timeout: Duration,
+    rebroadcast_interval: Option<Duration>,
+    last_valid_block_height: Option<u64>,
+) -> Result<u32, SmartTransactionError> {
+    let Some(rebroadcast_interval) = rebroadcast_interval else {
+        poll_transaction_confirmation(client, tx_sig, interval, timeout).await?;
+        return Ok(0);
+    };
+
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        preflight_commitment: Some(CommitmentLevel::Confirmed),
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+
+    let confirm = poll_transaction_confirmation(client, tx_sig, interval, timeout);
+    tokio::pin!(confirm);
+
+    let mut rebroadcasts = 0u32;
+    let next_rebroadcast = sleep(rebroadcast_interval);
+    tokio::pin!(next_rebroadcast);
+
+    loop {
+        tokio::select! {
+            result = &mut confirm => {
+                result?;
+                return Ok(rebroadcasts);
+            }
+            () = &mut next_rebroadcast => {
+                if let Some(last_valid_block_height) = last_valid_block_height {
+                    if client.get_block_height().await? > last_valid_block_height {
+                        return Err(SmartTransactionError::BlockhashExpired);
+                    }
+                }
+
+                match client.send_transaction_with_config(transaction, send_config).await {
+                    Ok(_) => rebroadcasts += 1,
+                    Err(e) => warn!(target: "log", "Rebroadcast of {} failed: {:?}", tx_sig, e),
+                }
+
+                next_rebroadcast.as_mut().reset(tokio::time::Instant::now() + rebroadcast_interval);
+            }
+        }
+    }
+}
+
 #[allow(clippy::result_large_err)]
 async fn simulate_transaction(
     client: &RpcClient,
@@ -0,0 +1,287 @@
+//
+// Copyright (c) Cryptic Dot
+//
+// Licensed under FusionAMM SDK Source-Available License v1.0
+// See the LICENSE file in the project root for license information.
+//
+
+use log::warn;
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{ClientConfig, Endpoint};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_streamer::tls_certificates::new_dummy_x509_certificate;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+const DEFAULT_LEADER_FANOUT: usize = 4;
+const DEFAULT_LEADER_CACHE_TTL_SECONDS: u64 = 30;
+
+#[derive(Clone)]
+pub struct SmartTxTpuConfig {
+    /// Leader/TPU address cache, shared across sends so the `getLeaderSchedule`/`getClusterNodes`
+    /// lookups are only refreshed once the cache's `ttl` elapses rather than on every send.
+    pub leader_cache: Arc<TpuLeaderCache>,
+    /// Number of upcoming slots whose leaders the transaction is fanned out to. Defaults to 4.
+    /// Consecutive slots often share a leader, so this usually reaches fewer distinct leaders.
+    pub leader_fanout: usize,
+    /// Optional identity used to set up the QUIC connection from a staked node, for better inclusion.
+    pub identity: Option<Arc<Keypair>>,
+}
+
+impl SmartTxTpuConfig {
+    pub fn new(client: Arc<RpcClient>) -> Self {
+        Self {
+            leader_cache: Arc::new(TpuLeaderCache::new(client, Duration::from_secs(DEFAULT_LEADER_CACHE_TTL_SECONDS))),
+            leader_fanout: DEFAULT_LEADER_FANOUT,
+            identity: None,
+        }
+    }
+}
+
+struct CachedLeaders {
+    fetched_at: Instant,
+    // Slot -> leader pubkey for the current epoch.
+    schedule: HashMap<u64, Pubkey>,
+    // Leader pubkey -> TPU (or TPU-forwards) QUIC socket address.
+    tpu_quic: HashMap<Pubkey, SocketAddr>,
+}
+
+/// Caches the mapping from slot leader to TPU QUIC socket address, refreshing it from
+/// `getLeaderSchedule`/`getClusterNodes` once `ttl` has elapsed.
+pub struct TpuLeaderCache {
+    client: Arc<RpcClient>,
+    ttl: Duration,
+    inner: RwLock<Option<CachedLeaders>>,
+}
+
+impl TpuLeaderCache {
+    pub fn new(client: Arc<RpcClient>, ttl: Duration) -> Self {
+        Self {
+            client,
+            ttl,
+            inner: RwLock::new(None),
+        }
+    }
+
+    /// Returns the TPU QUIC addresses of the leaders of the `fanout` slots starting at `from_slot`,
+    /// deduped in case consecutive slots share a leader.
+    pub async fn leader_tpu_addresses(&self, from_slot: u64, fanout: usize) -> Result<Vec<SocketAddr>, ClientError> {
+        self.refresh_if_stale().await?;
+
+        let guard = self.inner.read().await;
+        let cached = guard.as_ref().expect("leader cache is populated by refresh_if_stale");
+
+        let mut addresses = Vec::with_capacity(fanout);
+        for slot in from_slot..from_slot + fanout as u64 {
+            if let Some(addr) = cached.schedule.get(&slot).and_then(|leader| cached.tpu_quic.get(leader)) {
+                if !addresses.contains(addr) {
+                    addresses.push(*addr);
+                }
+            }
+        }
+
+        Ok(addresses)
+    }
+
+    async fn refresh_if_stale(&self) -> Result<(), ClientError> {
+        {
+            let guard = self.inner.read().await;
+            if let Some(cached) = guard.as_ref() {
+                if cached.fetched_at.elapsed() < self.ttl {
+                    return Ok(());
+                }
+            }
+        }
+
+        let epoch_info = self.client.get_epoch_info().await?;
+        let leader_schedule = self.client.get_leader_schedule(Some(epoch_info.absolute_slot)).await?.unwrap_or_default();
+        let first_slot_in_epoch = epoch_info.absolute_slot - epoch_info.slot_index;
+
+        let mut schedule = HashMap::new();
+        for (pubkey_str, slot_indices) in leader_schedule {
+            if let Ok(pubkey) = Pubkey::from_str(&pubkey_str) {
+                for slot_index in slot_indices {
+                    schedule.insert(first_slot_in_epoch + slot_index as u64, pubkey);
+                }
+            }
+        }
+
+        let cluster_nodes = self.client.get_cluster_nodes().await?;
+        let mut tpu_quic = HashMap::new();
+        for node in cluster_nodes {
+            if let (Ok(pubkey), Some(addr)) = (Pubkey::from_str(&node.pubkey), node.tpu_quic.or(node.tpu_forwards_quic)) {
+                tpu_quic.insert(pubkey, addr);
+            }
+        }
+
+        *self.inner.write().await = Some(CachedLeaders {
+            fetched_at: Instant::now(),
+            schedule,
+            tpu_quic,
+        });
+
+        Ok(())
+    }
+}
+
+/// Ships the serialized `VersionedTransaction` directly to the current and upcoming slot leaders
+/// over QUIC, bypassing `send_transaction_with_config`.
+pub async fn send_transaction_via_tpu(
+    leader_cache: &TpuLeaderCache,
+    from_slot: u64,
+    fanout: usize,
+    identity: Option<&Keypair>,
+    wire_transaction: &[u8],
+) -> Result<(), ClientError> {
+    let addresses = leader_cache.leader_tpu_addresses(from_slot, fanout).await?;
+    if addresses.is_empty() {
+        return Err(ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom("no TPU QUIC leaders resolved for the upcoming slots".to_string()),
+        });
+    }
+
+    let endpoint = build_quic_endpoint(identity)?;
+    let sends = addresses.into_iter().map(|addr| {
+        let endpoint = endpoint.clone();
+        async move { send_to_tpu_quic(&endpoint, addr, wire_transaction).await }
+    });
+
+    let results = futures::future::join_all(sends).await;
+
+    // Each send already awaits its own stream's delivery and drops its connection when it finishes;
+    // close the endpoint and wait for it to drain so no in-flight close/ack packets are lost before
+    // the caller moves on.
+    endpoint.close(0u32.into(), b"");
+    endpoint.wait_idle().await;
+
+    if let Some(err) = results.iter().find_map(|r| r.as_ref().err()) {
+        if results.iter().all(|r| r.is_err()) {
+            warn!(target: "log", "Failed to deliver transaction to any of the resolved TPU leaders");
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(err.to_string()),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn build_quic_endpoint(identity: Option<&Keypair>) -> Result<Endpoint, ClientError> {
+    let mut endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap()).map_err(|e| ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(format!("failed to bind QUIC client endpoint: {}", e)),
+    })?;
+
+    // Validator TPU QUIC servers present a self-signed certificate derived from their identity
+    // keypair, which chains to no CA, so the platform verifier can never accept it. Skip server
+    // verification instead, the same way `solana-quic-client` does.
+    let rustls_config = match identity {
+        // Derive the same self-signed client certificate from `identity` that the validator TPU
+        // client uses, so the connection is attributable to a staked node for better inclusion.
+        Some(identity) => {
+            let (certificate, key) = new_dummy_x509_certificate(identity);
+            rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+                .with_client_auth_cert(vec![certificate], key)
+                .map_err(|e| ClientError {
+                    request: None,
+                    kind: ClientErrorKind::Custom(format!("failed to set up staked identity client certificate: {}", e)),
+                })?
+        }
+        None => rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth(),
+    };
+
+    let quic_client_config = QuicClientConfig::try_from(rustls_config).map_err(|e| ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(format!("failed to build QUIC client crypto config: {}", e)),
+    })?;
+    endpoint.set_default_client_config(ClientConfig::new(Arc::new(quic_client_config)));
+
+    Ok(endpoint)
+}
+
+/// Accepts any server certificate, matching `solana-quic-client`'s behavior: validator TPU QUIC
+/// servers present self-signed certificates derived from their identity keypair, which chain to
+/// no CA and would otherwise fail verification against the OS trust store.
+#[derive(Debug)]
+struct SkipServerVerification;
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(&self, _message: &[u8], _cert: &CertificateDer<'_>, _dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(&self, _message: &[u8], _cert: &CertificateDer<'_>, _dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA1,
+            SignatureScheme::ECDSA_SHA1_Legacy,
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ED25519,
+        ]
+    }
+}
+
+async fn send_to_tpu_quic(endpoint: &Endpoint, addr: SocketAddr, wire_transaction: &[u8]) -> Result<(), ClientError> {
+    let quic_err = |context: &str, e: &dyn std::fmt::Display| ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(format!("{} ({}): {}", context, addr, e)),
+    };
+
+    let connection = endpoint
+        .connect(addr, "solana-tpu")
+        .map_err(|e| quic_err("failed to start QUIC connection", &e))?
+        .await
+        .map_err(|e| quic_err("QUIC connection failed", &e))?;
+
+    let mut send_stream = connection.open_uni().await.map_err(|e| quic_err("failed to open QUIC stream", &e))?;
+    send_stream.write_all(wire_transaction).await.map_err(|e| quic_err("failed to write transaction", &e))?;
+    send_stream.finish().map_err(|e| quic_err("failed to finish QUIC stream", &e))?;
+    // `finish()` only marks the stream complete locally; quinn still has to flush and get the FIN
+    // acknowledged. Without waiting here, the connection (and, once every send races through
+    // `join_all`, the endpoint) can be dropped before the bytes actually leave the socket.
+    send_stream
+        .stopped()
+        .await
+        .map_err(|e| quic_err("failed waiting for QUIC stream to be delivered", &e))?;
+
+    Ok(())
+}